@@ -1,5 +1,17 @@
 use super::storage::{BlockType, Address};
-use num_traits::{Zero, One, ToPrimitive};
+use num_traits::{Zero, One, ToPrimitive, PrimInt};
+
+/// Returns a mask with the low `bits` bits set and the rest clear.
+///
+/// Used to zero out the bits of a block that fall outside a bit vector's
+/// valid range, such as the excess high bits of a final partial block.
+fn low_mask<Block: BlockType>(bits: usize) -> Block {
+    if bits >= Block::nbits() {
+        !Block::zero()
+    } else {
+        (Block::one() << bits) - Block::one()
+    }
+}
 
 /// Read-only bit vector operations.
 ///
@@ -101,6 +113,297 @@ pub trait BitVec {
 
         (high_bits << margin) | low_bits
     }
+
+    /// Returns an iterator over the maximal runs of identical bits in
+    /// `[start, end)`.
+    ///
+    /// Each item is a [`Chunk`] covering a contiguous span where every bit
+    /// has the same value. This is far cheaper than scanning bit-by-bit for
+    /// vectors that are sparse or clustered, since whole blocks are read via
+    /// `get_block` and only `trailing_zeros()` is used to find where a run
+    /// ends within a block.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start > end` or `end > self.bit_len()`.
+    fn runs(&self, start: u64, end: u64) -> Runs<'_, Self> {
+        assert!(start <= end && end <= self.bit_len(),
+                "BitVec::runs: out of bounds");
+
+        Runs { bv: self, pos: start, end: end }
+    }
+
+    /// Returns the number of set bits in `[0, i)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i > self.bit_len()`.
+    fn rank_1(&self, i: u64) -> u64 {
+        assert!(i <= self.bit_len(), "BitVec::rank_1: out of bounds");
+
+        let bit_offset = self.bit_offset() as u64;
+        let address = Address::new::<Self::Block>(i + bit_offset);
+
+        let mut count: u64 = self.blocks().take(address.block_index)
+            .map(|block| block.count_ones() as u64)
+            .sum();
+
+        if address.bit_offset > 0 {
+            let mut block = self.get_block(address.block_index);
+            if address.block_index == 0 && bit_offset > 0 {
+                block = block & !low_mask::<Self::Block>(bit_offset as usize);
+            }
+            block = block & low_mask::<Self::Block>(address.bit_offset);
+            count += block.count_ones() as u64;
+        }
+
+        count
+    }
+
+    /// Returns the number of clear bits in `[0, i)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i > self.bit_len()`.
+    fn rank_0(&self, i: u64) -> u64 {
+        i - self.rank_1(i)
+    }
+
+    /// Returns the position of the `k`-th set bit (0-indexed), or `None` if
+    /// there are fewer than `k + 1` set bits.
+    fn select_1(&self, k: u64) -> Option<u64> {
+        let bit_offset = self.bit_offset() as u64;
+        let mut remaining = k;
+
+        for (block_index, block) in self.blocks().enumerate() {
+            let ones = block.count_ones() as u64;
+            if remaining < ones {
+                for bit in 0 .. Self::Block::nbits() {
+                    if block.get_bit(bit) {
+                        if remaining == 0 {
+                            let position =
+                                Self::Block::mul_nbits(block_index) + bit as u64 - bit_offset;
+                            return Some(position);
+                        }
+                        remaining -= 1;
+                    }
+                }
+                unreachable!("BitVec::select_1: popcount mismatch");
+            }
+
+            remaining -= ones;
+        }
+
+        None
+    }
+
+    /// Returns the position of the `k`-th clear bit (0-indexed), or `None`
+    /// if there are fewer than `k + 1` clear bits.
+    fn select_0(&self, k: u64) -> Option<u64> {
+        let bit_offset = self.bit_offset() as u64;
+        let block_len = self.block_len();
+        let mut remaining = k;
+
+        for block_index in 0 .. block_len {
+            let mut block = self.get_block(block_index);
+
+            if block_index == 0 && bit_offset > 0 {
+                block = block | low_mask::<Self::Block>(bit_offset as usize);
+            }
+
+            if block_index + 1 == block_len {
+                let valid_bits =
+                    Self::Block::last_block_bits(self.bit_len() + bit_offset);
+                block = block | !low_mask::<Self::Block>(valid_bits);
+            }
+
+            let zeros = block.count_zeros() as u64;
+            if remaining < zeros {
+                for bit in 0 .. Self::Block::nbits() {
+                    if !block.get_bit(bit) {
+                        if remaining == 0 {
+                            let position =
+                                Self::Block::mul_nbits(block_index) + bit as u64 - bit_offset;
+                            return Some(position);
+                        }
+                        remaining -= 1;
+                    }
+                }
+                unreachable!("BitVec::select_0: popcount mismatch");
+            }
+
+            remaining -= zeros;
+        }
+
+        None
+    }
+
+    /// Returns the number of set bits.
+    fn count_ones(&self) -> u64 {
+        self.blocks().map(|block| block.count_ones() as u64).sum()
+    }
+
+    /// Returns the number of clear bits.
+    fn count_zeros(&self) -> u64 {
+        self.bit_len() - self.count_ones()
+    }
+
+    /// True if every bit is set.
+    fn all(&self) -> bool {
+        self.count_ones() == self.bit_len()
+    }
+
+    /// True if at least one bit is set.
+    fn any(&self) -> bool {
+        self.count_ones() > 0
+    }
+
+    /// True if every bit is clear.
+    fn none(&self) -> bool {
+        !self.any()
+    }
+
+    /// Returns an iterator over the blocks of this bit vector, in order.
+    ///
+    /// The final block, if any, has its unused high bits masked to zero.
+    /// `count_ones`, `rank_1`, and `select_1` are built on this iterator to
+    /// avoid reading bits one at a time; `select_0` needs the opposite
+    /// masking (invalid bits treated as set, not clear) and works with
+    /// `get_block` directly instead. This also lets callers stream a bit
+    /// vector to or from disk or the network without poking individual
+    /// bits through `get_bit`.
+    fn blocks(&self) -> Blocks<'_, Self> {
+        Blocks { bv: self, index: 0, block_len: self.block_len() }
+    }
+
+    /// Converts this bit vector to bytes, most-significant-bit-first
+    /// within each byte, padding the final byte with zeros if
+    /// `self.bit_len()` is not a multiple of 8.
+    ///
+    /// The default implementation reads one bit at a time; consider it a
+    /// slow reference implementation, and override it.
+    fn to_bytes(&self) -> Vec<u8> {
+        let bit_len = self.bit_len();
+        let mut bytes = Vec::with_capacity(((bit_len + 7) / 8) as usize);
+
+        let mut byte = 0u8;
+        let mut filled = 0u8;
+
+        for i in 0 .. bit_len {
+            byte = (byte << 1) | self.get_bit(i) as u8;
+            filled += 1;
+
+            if filled == 8 {
+                bytes.push(byte);
+                byte = 0;
+                filled = 0;
+            }
+        }
+
+        if filled > 0 {
+            byte <<= 8 - filled;
+            bytes.push(byte);
+        }
+
+        bytes
+    }
+}
+
+/// A maximal run of identical bits, as yielded by [`BitVec::runs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Chunk {
+    /// A run of set bits, covering the given bit range.
+    Ones(::std::ops::Range<u64>),
+    /// A run of clear bits, covering the given bit range.
+    Zeros(::std::ops::Range<u64>),
+}
+
+/// An iterator over the maximal runs of identical bits in a `BitVec`,
+/// created by [`BitVec::runs`].
+pub struct Runs<'a, V: ?Sized + 'a> {
+    bv: &'a V,
+    pos: u64,
+    end: u64,
+}
+
+impl<'a, V: BitVec + ?Sized> Iterator for Runs<'a, V> {
+    type Item = Chunk;
+
+    fn next(&mut self) -> Option<Chunk> {
+        if self.pos >= self.end {
+            return None;
+        }
+
+        let start = self.pos;
+        let value = self.bv.get_bit(start);
+
+        let bit_offset = self.bv.bit_offset() as u64;
+        let address = Address::new::<V::Block>(start + bit_offset);
+        let mut block_index = address.block_index;
+        let mut shift = address.bit_offset;
+
+        let mut pos = start;
+
+        loop {
+            let block = self.bv.get_block(block_index);
+            let shifted = block >> shift;
+            let probe = if value { !shifted } else { shifted };
+
+            let remaining_in_block = V::Block::nbits() as u64 - shift as u64;
+            let run = ::std::cmp::min(probe.trailing_zeros() as u64, remaining_in_block);
+            let run = ::std::cmp::min(run, self.end - pos);
+
+            pos += run;
+
+            if run < remaining_in_block || pos >= self.end {
+                break;
+            }
+
+            block_index += 1;
+            shift = 0;
+        }
+
+        self.pos = pos;
+
+        Some(if value { Chunk::Ones(start .. pos) } else { Chunk::Zeros(start .. pos) })
+    }
+}
+
+/// An iterator over the blocks of a `BitVec`, created by [`BitVec::blocks`].
+pub struct Blocks<'a, V: ?Sized + 'a> {
+    bv: &'a V,
+    index: usize,
+    block_len: usize,
+}
+
+impl<'a, V: BitVec + ?Sized> Iterator for Blocks<'a, V> {
+    type Item = V::Block;
+
+    fn next(&mut self) -> Option<V::Block> {
+        if self.index >= self.block_len {
+            return None;
+        }
+
+        let bit_offset = self.bv.bit_offset() as u64;
+        let mut block = self.bv.get_block(self.index);
+
+        if self.index == 0 && bit_offset > 0 {
+            block = block & !low_mask::<V::Block>(bit_offset as usize);
+        }
+
+        if self.index + 1 == self.block_len {
+            let valid_bits = V::Block::last_block_bits(self.bv.bit_len() + bit_offset);
+            block = block & low_mask::<V::Block>(valid_bits);
+        }
+
+        self.index += 1;
+        Some(block)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.block_len - self.index;
+        (remaining, Some(remaining))
+    }
 }
 
 /// Mutable bit vector operations that don’t affect the length.
@@ -197,6 +500,134 @@ pub trait BitVecMut: BitVec {
         self.set_block(address.block_index, new_block1);
         self.set_block(address.block_index + 1, new_block2);
     }
+
+    /// Sets `len` bits starting at bit index `start` to `value`, a single
+    /// boolean repeated across the whole span.
+    ///
+    /// Unlike `set_bits`, which is capped at `Block::nbits()` bits of
+    /// payload, `set_range` handles spans of any length in O(blocks): the
+    /// leading and trailing partial blocks are written with masked
+    /// `with_bits` calls, and every whole block in between is written
+    /// directly with `set_block`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit span goes out of bounds.
+    fn set_range(&mut self, start: u64, len: u64, value: bool) {
+        let limit = start + len;
+        assert!(limit <= self.bit_len(), "BitVecMut::set_range: out of bounds");
+
+        if len == 0 {
+            return;
+        }
+
+        let bit_offset = self.bit_offset() as u64;
+        let fill = if value { !Self::Block::zero() } else { Self::Block::zero() };
+
+        let first = Address::new::<Self::Block>(start + bit_offset);
+        let last = Address::new::<Self::Block>(limit - 1 + bit_offset);
+
+        if first.block_index == last.block_index {
+            let old_block = self.get_block(first.block_index);
+            let new_block = old_block.with_bits(first.bit_offset, len as usize, fill);
+            self.set_block(first.block_index, new_block);
+            return;
+        }
+
+        let head_count = Self::Block::nbits() - first.bit_offset;
+        let old_head = self.get_block(first.block_index);
+        let new_head = old_head.with_bits(first.bit_offset, head_count, fill);
+        self.set_block(first.block_index, new_head);
+
+        for block_index in first.block_index + 1 .. last.block_index {
+            self.set_block(block_index, fill);
+        }
+
+        let tail_count = last.bit_offset + 1;
+        let old_tail = self.get_block(last.block_index);
+        let new_tail = old_tail.with_bits(0, tail_count, fill);
+        self.set_block(last.block_index, new_tail);
+    }
+
+    /// Applies `f` block-by-block to `self` and `other`, storing the result
+    /// in `self`. The shared primitive behind `and`, `or`, and `xor`.
+    ///
+    /// Combining blocks at the same index is only valid when `self` and
+    /// `other` agree on where bit 0 falls within block 0; otherwise block
+    /// `i` of each would represent different logical bit ranges.
+    ///
+    /// The unused high bits of a final partial block are left untouched:
+    /// `set_block`'s contract forbids implementations from writing them, so
+    /// there is nothing here to re-mask afterward.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self.bit_len() != other.bit_len()` or
+    /// `self.bit_offset() != other.bit_offset()`.
+    fn zip_block<Other, F>(&mut self, other: &Other, mut f: F)
+        where Other: BitVec<Block = Self::Block> + ?Sized,
+              F: FnMut(Self::Block, Self::Block) -> Self::Block {
+
+        assert!(self.bit_len() == other.bit_len(),
+                "BitVecMut::zip_block: length mismatch");
+        assert!(self.bit_offset() == other.bit_offset(),
+                "BitVecMut::zip_block: bit_offset mismatch");
+
+        for i in 0 .. self.block_len() {
+            let value = f(self.get_block(i), other.get_block(i));
+            self.set_block(i, value);
+        }
+    }
+
+    /// Computes the elementwise boolean AND of `self` and `other`, storing
+    /// the result in `self`. Equivalent to set intersection when both bit
+    /// vectors represent sets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self.bit_len() != other.bit_len()` or
+    /// `self.bit_offset() != other.bit_offset()`.
+    fn and<Other: BitVec<Block = Self::Block> + ?Sized>(&mut self, other: &Other) {
+        self.zip_block(other, |a, b| a & b);
+    }
+
+    /// Computes the elementwise boolean OR of `self` and `other`, storing
+    /// the result in `self`. Equivalent to set union.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self.bit_len() != other.bit_len()` or
+    /// `self.bit_offset() != other.bit_offset()`.
+    fn or<Other: BitVec<Block = Self::Block> + ?Sized>(&mut self, other: &Other) {
+        self.zip_block(other, |a, b| a | b);
+    }
+
+    /// Computes the elementwise boolean XOR of `self` and `other`, storing
+    /// the result in `self`. Equivalent to symmetric set difference.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self.bit_len() != other.bit_len()` or
+    /// `self.bit_offset() != other.bit_offset()`.
+    fn xor<Other: BitVec<Block = Self::Block> + ?Sized>(&mut self, other: &Other) {
+        self.zip_block(other, |a, b| a ^ b);
+    }
+
+    /// Flips every bit of `self` in place. The unused high bits of a final
+    /// partial block are masked to zero in the block handed to `set_block`,
+    /// so a `set_block` that writes the whole raw block leaves them clear.
+    fn not(&mut self) {
+        let block_len = self.block_len();
+        for i in 0 .. block_len {
+            let mut value = !self.get_block(i);
+            if i + 1 == block_len {
+                let valid_bits = Self::Block::last_block_bits(
+                    self.bit_len() + self.bit_offset() as u64);
+                value = value & low_mask::<Self::Block>(valid_bits);
+            }
+            self.set_block(i, value);
+        }
+    }
 }
 
 /// Bit vector operations that change the length.
@@ -230,6 +661,23 @@ pub trait BitVecPush: BitVecMut {
             value = value >> 1;
         }
     }
+
+    /// Builds a new value from `bytes`, interpreting each byte
+    /// most-significant-bit-first to match conventional external formats.
+    ///
+    /// The default implementation pushes one bit at a time; override it
+    /// with something more efficient.
+    fn from_bytes(bytes: &[u8]) -> Self where Self: Sized + Default {
+        let mut result = Self::default();
+
+        for &byte in bytes {
+            for i in (0 .. 8).rev() {
+                result.push_bit(byte & (1 << i) != 0);
+            }
+        }
+
+        result
+    }
 }
 
 /// Types that support (re-)slicing by ranges.
@@ -359,4 +807,221 @@ mod test {
         assert!(  v.get_bit(10) );
         assert!(  v.get_bit(11) );
     }
+
+    #[test]
+    fn vec_u8_logical_ops() {
+        let b = vec![0b10101010u8];
+
+        let mut a = vec![0b11001100u8];
+        a.and(&b[..]);
+        assert_eq!(a, vec![0b10001000u8]);
+
+        let mut c = vec![0b11001100u8];
+        c.or(&b[..]);
+        assert_eq!(c, vec![0b11101110u8]);
+
+        let mut d = vec![0b11001100u8];
+        d.xor(&b[..]);
+        assert_eq!(d, vec![0b01100110u8]);
+
+        let mut e = vec![0b11001100u8];
+        e.not();
+        assert_eq!(e, vec![0b00110011u8]);
+    }
+
+    #[test]
+    fn vec_u8_runs() {
+        // Bits 0..8, LSB first: false, false, true, true, true, false, true, true
+        let v = vec![0b11011100u8];
+
+        let runs: Vec<Chunk> = v.runs(0, 8).collect();
+        assert_eq!(runs, vec![
+            Chunk::Zeros(0 .. 2),
+            Chunk::Ones(2 .. 5),
+            Chunk::Zeros(5 .. 6),
+            Chunk::Ones(6 .. 8),
+        ]);
+
+        let runs: Vec<Chunk> = v.runs(3, 7).collect();
+        assert_eq!(runs, vec![
+            Chunk::Ones(3 .. 5),
+            Chunk::Zeros(5 .. 6),
+            Chunk::Ones(6 .. 7),
+        ]);
+    }
+
+    #[test]
+    fn vec_u8_rank_select() {
+        // Bits 0..8, LSB first: false, false, true, true, false, true, false, true
+        let v = vec![0b10101100u8];
+
+        assert_eq!(v.rank_1(0), 0);
+        assert_eq!(v.rank_1(3), 1);
+        assert_eq!(v.rank_1(8), 4);
+        assert_eq!(v.rank_0(8), 4);
+
+        assert_eq!(v.select_1(0), Some(2));
+        assert_eq!(v.select_1(1), Some(3));
+        assert_eq!(v.select_1(3), Some(7));
+        assert_eq!(v.select_1(4), None);
+
+        assert_eq!(v.select_0(0), Some(0));
+        assert_eq!(v.select_0(1), Some(1));
+        assert_eq!(v.select_0(3), Some(6));
+        assert_eq!(v.select_0(4), None);
+    }
+
+    /// A `BitVec` over raw `u8` blocks with an explicit `bit_len` and
+    /// `bit_offset`, used to exercise partial-block and bit-offset paths
+    /// that `Vec<u8>`/`[u8]` (always block-aligned, `bit_offset() == 0`)
+    /// can't reach.
+    struct TestBits {
+        blocks: Vec<u8>,
+        bit_len: u64,
+        bit_offset: u8,
+    }
+
+    impl BitVec for TestBits {
+        type Block = u8;
+
+        fn bit_len(&self) -> u64 { self.bit_len }
+        fn bit_offset(&self) -> u8 { self.bit_offset }
+        fn block_len(&self) -> usize { self.blocks.len() }
+
+        fn get_block(&self, position: usize) -> u8 {
+            self.blocks[position]
+        }
+    }
+
+    impl BitVecMut for TestBits {
+        fn set_block(&mut self, position: usize, value: u8) {
+            self.blocks[position] = value;
+        }
+    }
+
+    #[test]
+    fn partial_last_block_rank_select() {
+        // 11 valid bits across two blocks. The second block's high 5 bits
+        // are garbage past `bit_len` and must be masked out, not just for
+        // `count_ones`/`select_0` but also `select_1`.
+        let v = TestBits {
+            blocks: vec![0xffu8, 0b11111101u8],
+            bit_len: 11,
+            bit_offset: 0,
+        };
+
+        assert_eq!(v.count_ones(), 10);
+        assert_eq!(v.count_zeros(), 1);
+
+        assert_eq!(v.rank_1(9), 9);
+        assert_eq!(v.rank_0(10), 1);
+
+        assert_eq!(v.select_1(9), Some(10));
+        assert_eq!(v.select_1(10), None);
+
+        assert_eq!(v.select_0(0), Some(9));
+        assert_eq!(v.select_0(1), None);
+    }
+
+    #[test]
+    fn nonzero_bit_offset_rank_select() {
+        // A single block of 5 valid bits starting at bit_offset 3, i.e.
+        // logical bits 0..5 live at raw bit positions 3..8.
+        let v = TestBits {
+            blocks: vec![0b10110101u8],
+            bit_len: 5,
+            bit_offset: 3,
+        };
+
+        assert_eq!(v.count_ones(), 3);
+        assert_eq!(v.count_zeros(), 2);
+
+        assert_eq!(v.rank_1(3), 2);
+
+        assert_eq!(v.select_1(0), Some(1));
+        assert_eq!(v.select_1(2), Some(4));
+        assert_eq!(v.select_1(3), None);
+
+        assert_eq!(v.select_0(0), Some(0));
+        assert_eq!(v.select_0(1), Some(3));
+        assert_eq!(v.select_0(2), None);
+    }
+
+    #[test]
+    fn partial_last_block_not_clears_raw_garbage() {
+        // `TestBits::set_block` writes the whole raw block, like `[Block]`
+        // does, so the last block's unused high bits come out as whatever
+        // `not` hands it; `not` masks them to zero. `count_ones`, `select_1`,
+        // and `select_0` also mask the final block themselves.
+        let mut v = TestBits {
+            blocks: vec![0xffu8, 0b10100101u8],
+            bit_len: 11,
+            bit_offset: 0,
+        };
+
+        assert_eq!(v.count_ones(), 10);
+
+        v.not();
+
+        // The raw high bits of the last block are cleared, not left as
+        // whatever `!` produced.
+        assert_eq!(v.blocks[1], 0b00000010u8);
+
+        assert_eq!(v.count_ones(), 1);
+        assert_eq!(v.count_zeros(), 10);
+        assert_eq!(v.select_1(0), Some(9));
+        assert_eq!(v.select_1(1), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "bit_offset mismatch")]
+    fn zip_block_bit_offset_mismatch_panics() {
+        let mut a = TestBits {
+            blocks: vec![0b10110101u8],
+            bit_len: 5,
+            bit_offset: 3,
+        };
+        let b = TestBits {
+            blocks: vec![0b10110101u8],
+            bit_len: 5,
+            bit_offset: 0,
+        };
+
+        a.and(&b);
+    }
+
+    #[test]
+    fn vec_u8_set_range() {
+        let mut v = vec![0u8; 3];
+        v.set_range(3, 15, true);
+
+        for i in 0 .. 3 {
+            assert!( !v.get_bit(i) );
+        }
+        for i in 3 .. 18 {
+            assert!(  v.get_bit(i) );
+        }
+        for i in 18 .. 24 {
+            assert!( !v.get_bit(i) );
+        }
+
+        v.set_range(3, 15, false);
+        assert!(v.none());
+
+        v.set_range(0, 24, true);
+        assert!(v.all());
+        assert_eq!(v.count_ones(), 24);
+        assert_eq!(v.count_zeros(), 0);
+        assert!(v.any());
+    }
+
+    #[test]
+    fn vec_u8_bytes_and_blocks() {
+        let v = Vec::<bool>::from_bytes(&[0b01001000, 0b11100011]);
+        assert_eq!(v.bit_len(), 16);
+        assert_eq!(v.to_bytes(), vec![0b01001000, 0b11100011]);
+
+        let blocks: Vec<u8> = vec![0b01001000u8, 0b11100011u8].blocks().collect();
+        assert_eq!(blocks, vec![0b01001000u8, 0b11100011u8]);
+    }
 }